@@ -0,0 +1,74 @@
+//! Watches the configuration file on disk and debounces editor write bursts
+//! into a single reload signal.
+
+use atrofac_library::AfErr;
+use notify::{recommended_watcher, Event as NotifyEvent, RecommendedWatcher, RecursiveMode, Watcher};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+/// Coalescing window: a save that touches the file several times in a row
+/// (as most editors do) is folded into a single notification.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Watches `config_file` for changes and sends a unit value on `tx` at most
+/// once per debounce window. Watches the parent directory rather than the
+/// file itself, since a rename-over-save swaps the inode a direct watch
+/// would be bound to.
+pub struct ConfigWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl ConfigWatcher {
+    pub fn new(config_file: &Path, tx: Sender<()>) -> Result<Self, AfErr> {
+        let dir = config_file
+            .parent()
+            .ok_or_else(|| AfErr::from("Configuration file has no parent directory to watch."))?
+            .to_path_buf();
+        let file_name = config_file
+            .file_name()
+            .ok_or_else(|| AfErr::from("Configuration file path has no file name."))?
+            .to_owned();
+
+        let (raw_tx, raw_rx) = channel::<NotifyEvent>();
+        let mut watcher = recommended_watcher(move |res: notify::Result<NotifyEvent>| {
+            if let Ok(event) = res {
+                let _ = raw_tx.send(event);
+            }
+        })?;
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+
+        thread::spawn(move || debounce_loop(raw_rx, file_name, tx));
+
+        Ok(ConfigWatcher { _watcher: watcher })
+    }
+}
+
+/// Keeps only directory events that touch `file_name` and emits a single
+/// coalesced signal once the matching changes have been quiet for [`DEBOUNCE`].
+fn debounce_loop(raw_rx: Receiver<NotifyEvent>, file_name: OsString, tx: Sender<()>) {
+    let matches = |event: &NotifyEvent| {
+        event
+            .paths
+            .iter()
+            .any(|path: &PathBuf| path.file_name() == Some(file_name.as_os_str()))
+    };
+
+    loop {
+        // Block for the first matching event of a burst.
+        loop {
+            match raw_rx.recv() {
+                Ok(event) if matches(&event) => break,
+                Ok(_) => continue,
+                Err(_) => return,
+            }
+        }
+        // Then keep draining (matching or not) until the burst goes quiet.
+        while raw_rx.recv_timeout(DEBOUNCE).is_ok() {}
+        if tx.send(()).is_err() {
+            return;
+        }
+    }
+}
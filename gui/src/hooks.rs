@@ -0,0 +1,98 @@
+//! Runs a plan's optional `on_activate` / `on_deactivate` shell commands.
+
+use atrofac_library::AfErr;
+use std::io::Read;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const HOOK_TIMEOUT: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Which of a plan's two hooks a result came from, so the main loop can
+/// report the failure with the right label.
+#[derive(Clone, Copy)]
+pub enum HookKind {
+    Activate,
+    Deactivate,
+}
+
+impl HookKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            HookKind::Activate => "Plan activation hook failed",
+            HookKind::Deactivate => "Plan deactivation hook failed",
+        }
+    }
+}
+
+fn spawn_child(command: &str) -> Result<Child, AfErr> {
+    let result = if cfg!(windows) {
+        Command::new("cmd")
+            .args(["/C", command])
+            .stderr(Stdio::piped())
+            .spawn()
+    } else {
+        Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stderr(Stdio::piped())
+            .spawn()
+    };
+    result.map_err(|err| AfErr::from(format!("Hook command `{}` failed to start: {}", command, err)))
+}
+
+/// Runs `command` to completion, killing it if `HOOK_TIMEOUT` elapses first.
+fn run(command: &str) -> Result<(), AfErr> {
+    let mut child = spawn_child(command)?;
+    let start = Instant::now();
+
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                let mut stderr = String::new();
+                if let Some(mut stderr_pipe) = child.stderr.take() {
+                    let _ = stderr_pipe.read_to_string(&mut stderr);
+                }
+                return if status.success() {
+                    Ok(())
+                } else {
+                    Err(AfErr::from(format!(
+                        "Hook command `{}` exited with {}: {}",
+                        command, status, stderr
+                    )))
+                };
+            }
+            Ok(None) => {
+                if start.elapsed() >= HOOK_TIMEOUT {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(AfErr::from(format!(
+                        "Hook command `{}` timed out after {:?} and was killed",
+                        command, HOOK_TIMEOUT
+                    )));
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+            Err(err) => {
+                return Err(AfErr::from(format!(
+                    "Hook command `{}` failed while waiting for it to exit: {}",
+                    command, err
+                )));
+            }
+        }
+    }
+}
+
+/// Runs `hook` on a background thread if present, reporting the result on
+/// `tx` instead of blocking the caller.
+pub fn spawn_optional(hook: Option<&String>, kind: HookKind, tx: &Sender<(HookKind, Result<(), AfErr>)>) {
+    if let Some(command) = hook {
+        let command = command.clone();
+        let tx = tx.clone();
+        thread::spawn(move || {
+            let _ = tx.send((kind, run(&command)));
+        });
+    }
+}
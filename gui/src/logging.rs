@@ -0,0 +1,52 @@
+//! Structured logging to a rotating daily file.
+
+use atrofac_library::AfErr;
+use std::path::PathBuf;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
+
+const LOG_DIR_NAME: &str = "atrofac";
+const LOG_FILE_PREFIX: &str = "atrofac.log";
+
+/// Directory the rotating file appender writes into, under the user's local
+/// data dir.
+pub fn log_dir() -> Result<PathBuf, AfErr> {
+    dirs::data_local_dir()
+        .map(|dir| dir.join(LOG_DIR_NAME))
+        .ok_or_else(|| AfErr::from("Unable to determine the local data directory for logs."))
+}
+
+/// Path of today's active log file, for the "open log file" tray action.
+pub fn current_log_file() -> Result<PathBuf, AfErr> {
+    let today = time::OffsetDateTime::now_utc();
+    let file_name = format!(
+        "{}.{:04}-{:02}-{:02}",
+        LOG_FILE_PREFIX,
+        today.year(),
+        today.month() as u8,
+        today.day()
+    );
+    Ok(log_dir()?.join(file_name))
+}
+
+/// Initializes the global tracing subscriber. The returned [`WorkerGuard`]
+/// must be kept alive for the program's duration, or buffered lines are
+/// dropped on exit.
+pub fn init() -> Result<WorkerGuard, AfErr> {
+    let dir = log_dir()?;
+    std::fs::create_dir_all(&dir)?;
+    let file_appender = rolling::daily(&dir, LOG_FILE_PREFIX);
+    let (writer, guard) = tracing_appender::non_blocking(file_appender);
+
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_env("ATROFAC_LOG").unwrap_or_else(|_| EnvFilter::new("info")))
+        .with_writer(writer)
+        .with_ansi(false)
+        // log each #[instrument]-ed span's duration when it closes
+        .with_span_events(FmtSpan::CLOSE)
+        .init();
+
+    Ok(guard)
+}
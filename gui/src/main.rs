@@ -1,32 +1,79 @@
 use atrofac_libgui::engine::Engine;
 use atrofac_libgui::system::{
-    new_system_interface, MenuItem, MenuItemIdx, MenuItemState, StringMenuItem, SystemEvent,
-    SystemInterface,
+    new_system_interface, MenuItem, MenuItemIdx, MenuItemState, PowerSource, StringMenuItem,
+    SystemEvent, SystemInterface,
 };
 use atrofac_library::AfErr;
 use std::borrow::Cow;
 use std::convert::TryFrom;
 use std::time::Duration;
 
+mod config_watch;
+mod hooks;
+mod logging;
+mod power_source;
+mod tui_editor;
+mod updater;
+
+use config_watch::ConfigWatcher;
+use std::sync::mpsc::{channel, Sender, TryRecvError};
+use updater::UpdateStatus;
+
 const MENU_ITEM_RELOAD_CONFIG_OFFSET: usize = 1;
 const MENU_ITEM_EDIT_CONFIG_OFFSET: usize = 2;
-const MENU_ITEM_EDIT_EXIT_OFFSET: usize = 3;
+const MENU_ITEM_CHECK_UPDATE_OFFSET: usize = 3;
+const MENU_ITEM_OPEN_LOG_OFFSET: usize = 4;
+const MENU_ITEM_EDIT_EXIT_OFFSET: usize = 5;
 const DEFAULT_INTERVAL_SEC: u32 = 120;
+// how often an automatic (non-user-initiated) update check piggybacks on the timer tick
+const UPDATE_CHECK_INTERVAL_TICKS: u32 = 30;
+// `system.receive_event()` blocks until the next timer tick or tray click, so this is
+// also the longest an on-disk config edit can take to be picked up: the main loop
+// drains the `ConfigWatcher` channel on every tick, not just on the plan's own
+// (much longer) reapply interval, so keep this short rather than feel-live only
+// when something else happens to wake the loop.
+const CONFIG_POLL_INTERVAL_SEC: u32 = 2;
 
+#[tracing::instrument(skip(engine, system))]
 fn apply(engine: &mut Engine, system: &mut impl SystemInterface) -> Result<(), AfErr> {
     engine.apply()?;
+    tracing::debug!("applied fan curve and power profile");
+    system.set_timer(Duration::from_secs(CONFIG_POLL_INTERVAL_SEC as u64))?;
+    Ok(())
+}
 
-    // set the timer
+/// Ticks (each [`CONFIG_POLL_INTERVAL_SEC`] long) a plan's own reapply interval takes
+/// to elapse, rounded down to at least one so a very short interval still reapplies
+/// every tick rather than never.
+fn apply_interval_ticks(engine: &Engine) -> u32 {
+    let interval_secs = engine
+        .active_plan()
+        .and_then(|plan| plan.update_interval_sec)
+        .unwrap_or(DEFAULT_INTERVAL_SEC);
+    (interval_secs / CONFIG_POLL_INTERVAL_SEC).max(1)
+}
+
+/// Spawns `previous_plan`'s `on_deactivate` hook and the new active plan's
+/// `on_activate` hook around a plan switch, reporting results on `hook_tx`
+/// instead of blocking the caller.
+fn run_plan_transition_hooks(
+    previous_plan: Option<&atrofac_library::Plan>,
+    engine: &Engine,
+    hook_tx: &Sender<(hooks::HookKind, Result<(), AfErr>)>,
+) {
+    if let Some(previous_plan) = previous_plan {
+        hooks::spawn_optional(previous_plan.on_deactivate.as_ref(), hooks::HookKind::Deactivate, hook_tx);
+    }
     if let Some(active_plan) = engine.active_plan() {
-        let interval_secs = active_plan
-            .update_interval_sec
-            .unwrap_or(DEFAULT_INTERVAL_SEC);
-        system.set_timer(Duration::from_secs(interval_secs as u64))?;
+        hooks::spawn_optional(active_plan.on_activate.as_ref(), hooks::HookKind::Activate, hook_tx);
     }
-    Ok(())
 }
 
-fn load_tray(engine: &Engine, system: &mut impl SystemInterface) -> Result<(), AfErr> {
+fn load_tray(
+    engine: &Engine,
+    system: &mut impl SystemInterface,
+    update_status: &UpdateStatus,
+) -> Result<(), AfErr> {
     system.tray_clear()?;
 
     let active_plan = engine.active_plan();
@@ -54,6 +101,14 @@ fn load_tray(engine: &Engine, system: &mut impl SystemInterface) -> Result<(), A
         text: "Edit configuration".into(),
         state: MenuItemState::Default,
     }))?;
+    system.tray_add(MenuItem::String(StringMenuItem {
+        text: Cow::Owned(update_status.menu_text()),
+        state: update_status.menu_state(),
+    }))?;
+    system.tray_add(MenuItem::String(StringMenuItem {
+        text: "Open log file".into(),
+        state: MenuItemState::Default,
+    }))?;
     system.tray_add(MenuItem::String(StringMenuItem {
         text: "Quit application".into(),
         state: MenuItemState::Default,
@@ -61,11 +116,16 @@ fn load_tray(engine: &Engine, system: &mut impl SystemInterface) -> Result<(), A
     Ok(())
 }
 
+#[tracing::instrument(skip(engine, system, update_status, update_tx, hook_tx))]
 fn on_tray(
     menu_item_id: MenuItemIdx,
     engine: &mut Engine,
     system: &mut impl SystemInterface,
+    update_status: &mut UpdateStatus,
+    update_tx: &Sender<UpdateStatus>,
+    hook_tx: &Sender<(hooks::HookKind, Result<(), AfErr>)>,
 ) -> Result<(), AfErr> {
+    tracing::info!("tray menu item {} selected", menu_item_id.id());
     let index_usize = usize::try_from(menu_item_id.id())?;
     let number_of_plans = engine.number_of_plans();
     if index_usize >= number_of_plans {
@@ -74,13 +134,27 @@ fn on_tray(
         match offset {
             MENU_ITEM_RELOAD_CONFIG_OFFSET => {
                 engine.load_configuration()?;
-                load_tray(engine, system)?;
+                tracing::info!("configuration reloaded from tray menu");
+                load_tray(engine, system, update_status)?;
                 apply(engine, system)?;
                 Ok(())
             }
             MENU_ITEM_EDIT_CONFIG_OFFSET => {
-                let config_file = engine.config_file();
-                system.edit(config_file)?;
+                if tui_editor::edit_active_plan(engine)? {
+                    engine.save_configuration()?;
+                    engine.load_configuration()?;
+                    tracing::info!("configuration saved from the TUI fan-curve editor");
+                    apply(engine, system)?;
+                    load_tray(engine, system, update_status)?;
+                }
+                Ok(())
+            }
+            MENU_ITEM_CHECK_UPDATE_OFFSET => {
+                updater::spawn_check(env!("CARGO_PKG_VERSION"), update_tx.clone());
+                Ok(())
+            }
+            MENU_ITEM_OPEN_LOG_OFFSET => {
+                system.edit(&logging::current_log_file()?)?;
                 Ok(())
             }
             MENU_ITEM_EDIT_EXIT_OFFSET => {
@@ -92,12 +166,14 @@ fn on_tray(
     } else {
         // it's a plan
         if let Some(plan_name) = engine.plan_by_index(menu_item_id.id() as usize).cloned() {
+            let previous_plan = engine.active_plan().cloned();
             engine.set_active_plan(plan_name);
+            run_plan_transition_hooks(previous_plan.as_ref(), engine, hook_tx);
             // when the plan has been changed, save the configuration
             engine.save_configuration()?;
             apply(engine, system)?;
             // reload tray
-            load_tray(engine, system)?;
+            load_tray(engine, system, update_status)?;
             Ok(())
         } else {
             Err(AfErr::from(format!(
@@ -108,25 +184,152 @@ fn on_tray(
     }
 }
 
+fn on_power_source_changed(
+    power_source: PowerSource,
+    engine: &mut Engine,
+    system: &mut impl SystemInterface,
+    update_status: &UpdateStatus,
+    hook_tx: &Sender<(hooks::HookKind, Result<(), AfErr>)>,
+) -> Result<(), AfErr> {
+    let plan_name = match power_source {
+        PowerSource::Ac => engine.on_ac_plan(),
+        PowerSource::Battery => engine.on_battery_plan(),
+    }
+    .cloned();
+    let previous_plan = engine.active_plan().cloned();
+
+    // a manually selected plan keeps applying until the next transition, so a
+    // transition with no binding configured is a no-op rather than an error;
+    // likewise, Windows is known to repeat a power-broadcast notification for a
+    // single transition, so skip the switch entirely if it wouldn't change anything
+    let plan_changed = match (&plan_name, &previous_plan) {
+        (Some(plan_name), Some(previous_plan)) => *plan_name != previous_plan.name,
+        (Some(_), None) => true,
+        (None, _) => false,
+    };
+
+    if plan_changed {
+        engine.set_active_plan(plan_name.unwrap());
+        run_plan_transition_hooks(previous_plan.as_ref(), engine, hook_tx);
+        apply(engine, system)?;
+        load_tray(engine, system, update_status)?;
+    }
+    Ok(())
+}
+
 fn run_main_with_error(
     engine: &mut Engine,
     system: &mut impl SystemInterface,
 ) -> Result<(), AfErr> {
+    let mut update_status = UpdateStatus::UpToDate;
+    let mut ticks_since_update_check: u32 = 0;
+    let mut ticks_since_apply: u32 = 0;
+    let (update_tx, update_rx) = channel();
+
     engine.load_configuration()?;
+    tracing::info!("configuration loaded at startup");
     apply(engine, system)?;
-    load_tray(engine, system)?;
+    load_tray(engine, system, &update_status)?;
     system.tray_tooltip("Control fan curve and power profile for Asus Zephyrus ROG G14.")?;
 
+    // watch the config file so external edits are picked up without a manual reload
+    let (config_changed_tx, config_changed_rx) = channel();
+    let _config_watcher = ConfigWatcher::new(engine.config_file(), config_changed_tx)?;
+
+    // watch AC/battery transitions so the configured plan bindings can follow them
+    let (power_source_tx, power_source_rx) = channel();
+    power_source::spawn_watcher(power_source_tx)?;
+
+    // receives plan activation/deactivation hook results from background threads
+    let (hook_tx, hook_rx) = channel();
+
+    // the subscription above only reports future transitions; seed the loop with
+    // where we already are
+    let initial_power_source = power_source::current_power_source()?;
+    let mut last_power_source = std::mem::discriminant(&initial_power_source);
+    on_power_source_changed(
+        initial_power_source,
+        engine,
+        system,
+        &update_status,
+        &hook_tx,
+    )?;
+
     // loop
     loop {
+        if let Ok(power_source) = power_source_rx.try_recv() {
+            // a repeated notification for the source we are already on is not a
+            // transition, so it must not override a manually selected plan
+            if std::mem::discriminant(&power_source) != last_power_source {
+                last_power_source = std::mem::discriminant(&power_source);
+                on_power_source_changed(power_source, engine, system, &update_status, &hook_tx)?;
+            }
+            continue;
+        }
+
+        if let Ok((kind, result)) = hook_rx.try_recv() {
+            if let Err(err) = result {
+                let _ = system.show_err_message(kind.label(), &format!("{}", err));
+            }
+            continue;
+        }
+
+        match config_changed_rx.try_recv() {
+            Ok(()) => {
+                // an unattended, file-watcher-triggered reload must not take the tray
+                // down on a transient parse failure; log it and keep running with
+                // whatever configuration was already loaded
+                if let Err(err) = engine.load_configuration() {
+                    tracing::warn!(error = %err, "failed to reload configuration after an on-disk change");
+                    continue;
+                }
+                tracing::info!("configuration reloaded after an on-disk change");
+                load_tray(engine, system, &update_status)?;
+                if let Err(err) = apply(engine, system) {
+                    tracing::warn!(error = %err, "apply failed after an on-disk config change");
+                }
+                continue;
+            }
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => {}
+        }
+
+        if let Ok(status) = update_rx.try_recv() {
+            update_status = status;
+            load_tray(engine, system, &update_status)?;
+            continue;
+        }
+
         let event = system.receive_event()?;
         if let Some(event) = event {
             match event {
                 SystemEvent::OnTimer => {
-                    apply(engine, system)?;
+                    ticks_since_update_check += 1;
+                    if ticks_since_update_check >= UPDATE_CHECK_INTERVAL_TICKS {
+                        ticks_since_update_check = 0;
+                        updater::spawn_check(env!("CARGO_PKG_VERSION"), update_tx.clone());
+                    }
+
+                    // the timer now also ticks at CONFIG_POLL_INTERVAL_SEC so the loop
+                    // keeps coming back to drain `config_changed_rx`; only actually
+                    // reapply once the plan's own (usually much longer) interval elapses
+                    ticks_since_apply += 1;
+                    if ticks_since_apply >= apply_interval_ticks(engine) {
+                        // a transient EC/ACPI failure on a timer tick shouldn't take the
+                        // tray down; log it and retry on the next tick instead
+                        match apply(engine, system) {
+                            Ok(()) => ticks_since_apply = 0,
+                            Err(err) => {
+                                tracing::warn!(error = %err, "apply failed on timer tick, will retry");
+                                system.set_timer(Duration::from_secs(CONFIG_POLL_INTERVAL_SEC as u64))?;
+                            }
+                        }
+                    } else {
+                        system.set_timer(Duration::from_secs(CONFIG_POLL_INTERVAL_SEC as u64))?;
+                    }
                 }
                 SystemEvent::OnTray(menu_item_id) => {
-                    on_tray(menu_item_id, engine, system)?;
+                    on_tray(menu_item_id, engine, system, &mut update_status, &update_tx, &hook_tx)?;
                 }
             }
         } else {
@@ -145,6 +348,17 @@ fn run_main(engine: &mut Engine, system: &mut impl SystemInterface) {
 }
 
 fn main() {
+    let _log_guard = logging::init().expect("Unable to initialize logging");
+
+    match updater::apply_staged_update_if_present() {
+        Ok(true) => {
+            // the updated binary has been swapped in and relaunched; let this process exit
+            return;
+        }
+        Ok(false) => {}
+        Err(err) => tracing::error!(error = %err, "failed to apply a staged self-update"),
+    }
+
     let mut system = new_system_interface().expect("Unable to create system interface");
     let mut engine = Engine::new().expect("Unable to create engine.");
     run_main(&mut engine, &mut system);
@@ -0,0 +1,110 @@
+//! Watches Windows power-source transitions (AC <-> battery) and reports
+//! them on a channel, the same shape as [`crate::config_watch::ConfigWatcher`].
+
+use atrofac_libgui::system::PowerSource;
+use atrofac_library::AfErr;
+use std::sync::mpsc::Sender;
+use std::thread;
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::System::Power::{
+    GetSystemPowerStatus, RegisterPowerSettingNotification, GUID_ACDC_POWER_SOURCE,
+    POWERBROADCAST_SETTING, SYSTEM_POWER_STATUS,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DefWindowProcW, DispatchMessageW, GetMessageW, RegisterClassW, TranslateMessage,
+    DEVICE_NOTIFY_WINDOW_HANDLE, HWND_MESSAGE, MSG, PBT_POWERSETTINGCHANGE, WINDOW_EX_STYLE,
+    WM_POWERBROADCAST, WNDCLASSW, WS_OVERLAPPED,
+};
+
+/// Maps the raw `SYSTEM_POWER_STATUS.ACLineStatus` byte (1 = online, 0/255 =
+/// offline/unknown) to our [`PowerSource`]; unknown is treated as battery so
+/// a misreported transition only ever makes the fan curve more conservative.
+fn power_source_from_ac_line_status(ac_line_status: u8) -> PowerSource {
+    if ac_line_status == 1 {
+        PowerSource::Ac
+    } else {
+        PowerSource::Battery
+    }
+}
+
+/// Reads the current power source synchronously, for the startup snapshot.
+pub fn current_power_source() -> Result<PowerSource, AfErr> {
+    let mut status = SYSTEM_POWER_STATUS::default();
+    unsafe { GetSystemPowerStatus(&mut status) }?;
+    Ok(power_source_from_ac_line_status(status.ACLineStatus))
+}
+
+/// Spawns a hidden message-only window on a background thread, subscribes it
+/// to `GUID_ACDC_POWER_SOURCE` changes, and forwards each transition on `tx`.
+pub fn spawn_watcher(tx: Sender<PowerSource>) -> Result<(), AfErr> {
+    thread::spawn(move || {
+        if let Err(err) = run_message_loop(tx) {
+            tracing::error!(error = %err, "power-source watcher thread exited");
+        }
+    });
+    Ok(())
+}
+
+fn run_message_loop(tx: Sender<PowerSource>) -> Result<(), AfErr> {
+    unsafe {
+        let class_name = windows::core::w!("AtrofacPowerSourceWatcher");
+        let wnd_class = WNDCLASSW {
+            lpfnWndProc: Some(window_proc),
+            lpszClassName: class_name,
+            ..Default::default()
+        };
+        RegisterClassW(&wnd_class);
+
+        let hwnd = CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            class_name,
+            windows::core::w!(""),
+            WS_OVERLAPPED,
+            0,
+            0,
+            0,
+            0,
+            HWND_MESSAGE,
+            None,
+            None,
+            None,
+        );
+
+        // stash the sender where `window_proc` can reach it; see WATCHER_TX below
+        WATCHER_TX.with(|cell| *cell.borrow_mut() = Some(tx));
+
+        RegisterPowerSettingNotification(HWND(hwnd.0), &GUID_ACDC_POWER_SOURCE, DEVICE_NOTIFY_WINDOW_HANDLE)?;
+
+        let mut msg = MSG::default();
+        while GetMessageW(&mut msg, None, 0, 0).into() {
+            TranslateMessage(&msg);
+            DispatchMessageW(&msg);
+        }
+    }
+    Ok(())
+}
+
+thread_local! {
+    static WATCHER_TX: std::cell::RefCell<Option<Sender<PowerSource>>> = std::cell::RefCell::new(None);
+}
+
+unsafe extern "system" fn window_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if msg == WM_POWERBROADCAST && wparam.0 as u32 == PBT_POWERSETTINGCHANGE {
+        let setting = &*(lparam.0 as *const POWERBROADCAST_SETTING);
+        if setting.PowerSetting == GUID_ACDC_POWER_SOURCE && !setting.Data.is_empty() {
+            // this payload is a SYSTEM_POWER_CONDITION (0 = AC, 1 = battery, 2 = short-term
+            // source such as a UPS), not an ACLineStatus byte; anything but AC counts as battery
+            let power_source = if setting.Data[0] == 0 {
+                PowerSource::Ac
+            } else {
+                PowerSource::Battery
+            };
+            WATCHER_TX.with(|cell| {
+                if let Some(tx) = cell.borrow().as_ref() {
+                    let _ = tx.send(power_source);
+                }
+            });
+        }
+    }
+    DefWindowProcW(hwnd, msg, wparam, lparam)
+}
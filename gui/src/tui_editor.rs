@@ -0,0 +1,196 @@
+//! In-process terminal UI for editing a plan's fan curve and power profile,
+//! spawned from the tray instead of shelling out to an external text editor.
+
+use atrofac_libgui::engine::Engine;
+use atrofac_library::AfErr;
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, ExecutableCommand};
+use std::io::stdout;
+use std::time::Duration;
+use tui::backend::CrosstermBackend;
+use tui::layout::{Constraint, Direction, Layout};
+use tui::style::{Color, Modifier, Style};
+use tui::widgets::{Block, Borders, Cell, Paragraph, Row, Table};
+use tui::Terminal;
+
+/// A single, editable temperature/duty-cycle point of a fan curve.
+struct CurvePoint {
+    temperature: u8,
+    duty_percent: u8,
+}
+
+/// Checks that temperatures strictly increase and duty cycles never decrease,
+/// which is the invariant the EC firmware expects from a fan table.
+fn is_monotonic(points: &[CurvePoint]) -> bool {
+    points.windows(2).all(|pair| {
+        pair[0].temperature < pair[1].temperature && pair[0].duty_percent <= pair[1].duty_percent
+    })
+}
+
+/// Restores cooked mode and leaves the alternate screen on drop, so an early
+/// `?` or panic doesn't leave the terminal stuck.
+struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = stdout().execute(LeaveAlternateScreen);
+    }
+}
+
+/// Runs the fan-curve editor for the engine's currently active plan. Returns
+/// `Ok(true)` if the user saved (caller should persist via
+/// [`Engine::save_configuration`] and re-apply), `Ok(false)` if cancelled.
+pub fn edit_active_plan(engine: &mut Engine) -> Result<bool, AfErr> {
+    let (mut points, mut profile_index) = match engine.active_plan() {
+        Some(plan) => {
+            let points = plan
+                .fan_curve
+                .iter()
+                .map(|p| CurvePoint {
+                    temperature: p.temperature,
+                    duty_percent: p.duty_percent,
+                })
+                .collect();
+            let profile_index = atrofac_library::POWER_PROFILES
+                .iter()
+                .position(|name| *name == plan.power_profile)
+                .unwrap_or(0);
+            (points, profile_index)
+        }
+        None => return Ok(false),
+    };
+
+    enable_raw_mode()?;
+    let _terminal_guard = TerminalGuard;
+    execute!(stdout(), EnterAlternateScreen)?;
+    let backend = CrosstermBackend::new(stdout());
+    let mut terminal = Terminal::new(backend)?;
+
+    let mut selected = 0usize;
+    let saved = run_editor_loop(&mut terminal, &mut points, &mut profile_index, &mut selected)?;
+
+    if saved {
+        if let Some(plan) = engine.active_plan_mut() {
+            plan.fan_curve = points
+                .into_iter()
+                .map(|p| atrofac_library::FanCurvePoint {
+                    temperature: p.temperature,
+                    duty_percent: p.duty_percent,
+                })
+                .collect();
+            plan.power_profile = atrofac_library::POWER_PROFILES[profile_index].to_owned();
+        }
+    }
+
+    Ok(saved)
+}
+
+/// The interactive draw/input loop, isolated so an early `?` here still
+/// returns through the caller's [`TerminalGuard`].
+fn run_editor_loop(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    points: &mut Vec<CurvePoint>,
+    profile_index: &mut usize,
+    selected: &mut usize,
+) -> Result<bool, AfErr> {
+    loop {
+        terminal.draw(|frame| {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(3), Constraint::Length(3), Constraint::Length(3)])
+                .split(frame.size());
+
+            let rows = points.iter().enumerate().map(|(idx, point)| {
+                let style = if idx == *selected {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                Row::new(vec![
+                    Cell::from(format!("{}\u{00b0}C", point.temperature)),
+                    Cell::from(format!("{}%", point.duty_percent)),
+                ])
+                .style(style)
+            });
+
+            let table = Table::new(rows)
+                .header(Row::new(vec!["Temperature", "Duty cycle"]).style(Style::default().fg(Color::Yellow)))
+                .block(Block::default().borders(Borders::ALL).title("Fan curve"))
+                .widths(&[Constraint::Percentage(50), Constraint::Percentage(50)]);
+            frame.render_widget(table, chunks[0]);
+
+            let profile = Paragraph::new(atrofac_library::POWER_PROFILES[*profile_index])
+                .block(Block::default().borders(Borders::ALL).title("Power profile (Tab to cycle)"));
+            frame.render_widget(profile, chunks[1]);
+
+            let help = Block::default().borders(Borders::ALL).title(
+                "↑/↓ select  ←/→ duty  Shift+←/→ temp  +/- add/remove  Tab profile  s save  Esc cancel",
+            );
+            frame.render_widget(help, chunks[2]);
+        })?;
+
+        if event::poll(Duration::from_millis(200))? {
+            if let Event::Key(key) = event::read()? {
+                let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+                match key.code {
+                    KeyCode::Up => *selected = selected.saturating_sub(1),
+                    KeyCode::Down => {
+                        if *selected + 1 < points.len() {
+                            *selected += 1;
+                        }
+                    }
+                    KeyCode::Right if shift => {
+                        if let Some(point) = points.get_mut(*selected) {
+                            point.temperature = point.temperature.saturating_add(1);
+                        }
+                    }
+                    KeyCode::Left if shift => {
+                        if let Some(point) = points.get_mut(*selected) {
+                            point.temperature = point.temperature.saturating_sub(1);
+                        }
+                    }
+                    KeyCode::Right => {
+                        if let Some(point) = points.get_mut(*selected) {
+                            point.duty_percent = point.duty_percent.saturating_add(1).min(100);
+                        }
+                    }
+                    KeyCode::Left => {
+                        if let Some(point) = points.get_mut(*selected) {
+                            point.duty_percent = point.duty_percent.saturating_sub(1);
+                        }
+                    }
+                    KeyCode::Tab => {
+                        *profile_index = (*profile_index + 1) % atrofac_library::POWER_PROFILES.len();
+                    }
+                    KeyCode::BackTab => {
+                        *profile_index = profile_index
+                            .checked_sub(1)
+                            .unwrap_or(atrofac_library::POWER_PROFILES.len() - 1);
+                    }
+                    KeyCode::Char('+') => {
+                        let temperature = points.last().map(|p| p.temperature.saturating_add(5)).unwrap_or(40);
+                        points.push(CurvePoint {
+                            temperature,
+                            duty_percent: 0,
+                        });
+                    }
+                    KeyCode::Char('-') => {
+                        if points.len() > 2 {
+                            points.remove((*selected).min(points.len() - 1));
+                            *selected = (*selected).min(points.len() - 1);
+                        }
+                    }
+                    KeyCode::Char('s') | KeyCode::Enter => {
+                        if is_monotonic(points) {
+                            return Ok(true);
+                        }
+                    }
+                    KeyCode::Esc | KeyCode::Char('q') => return Ok(false),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
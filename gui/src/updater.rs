@@ -0,0 +1,193 @@
+//! Background self-update checker: queries the GitHub releases API and, if a
+//! newer release exists, downloads and verifies the Windows binary for the
+//! next launch to swap in.
+
+use atrofac_library::AfErr;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+use std::thread;
+
+const RELEASES_URL: &str = "https://api.github.com/repos/nickweiminhan/atrofac/releases/latest";
+/// Name of the companion checksums asset a release publishes alongside its
+/// binaries, in `sha256sum`-compatible format.
+const CHECKSUMS_ASSET_NAME: &str = "SHA256SUMS";
+
+/// Result of a single update check, reflected as tray menu item text/state.
+#[derive(Clone, Debug)]
+pub enum UpdateStatus {
+    UpToDate,
+    UpdateAvailable { version: String },
+    Downloading { version: String },
+    Ready { version: String },
+    Error(String),
+}
+
+impl UpdateStatus {
+    pub fn menu_text(&self) -> String {
+        match self {
+            UpdateStatus::UpToDate => "Check for updates".to_owned(),
+            UpdateStatus::UpdateAvailable { version } => format!("Update available: {}", version),
+            UpdateStatus::Downloading { version } => format!("Downloading {}...", version),
+            UpdateStatus::Ready { version } => format!("Update {} ready - restart to apply", version),
+            UpdateStatus::Error(_) => "Update check failed".to_owned(),
+        }
+    }
+
+    /// Reuses the tray's `Checked` state to flag that this item wants attention.
+    pub fn menu_state(&self) -> atrofac_libgui::system::MenuItemState {
+        use atrofac_libgui::system::MenuItemState;
+        match self {
+            UpdateStatus::UpToDate => MenuItemState::Default,
+            UpdateStatus::UpdateAvailable { .. }
+            | UpdateStatus::Downloading { .. }
+            | UpdateStatus::Ready { .. }
+            | UpdateStatus::Error(_) => MenuItemState::Checked,
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<ReleaseAsset>,
+}
+
+#[derive(serde::Deserialize)]
+struct ReleaseAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// Spawns a background thread that checks GitHub for a newer release than
+/// `current_version` and reports each step back through `tx`.
+pub fn spawn_check(current_version: &'static str, tx: Sender<UpdateStatus>) {
+    thread::spawn(move || {
+        let status = check_and_download(current_version, &tx).unwrap_or_else(UpdateStatus::Error);
+        let _ = tx.send(status);
+    });
+}
+
+/// Parses a dotted version string into numeric segments for comparison; a
+/// non-numeric segment is treated as `0`.
+fn version_segments(version: &str) -> Vec<u64> {
+    version.split('.').map(|part| part.parse().unwrap_or(0)).collect()
+}
+
+fn is_newer(candidate: &str, baseline: &str) -> bool {
+    version_segments(candidate) > version_segments(baseline)
+}
+
+fn check_and_download(current_version: &str, tx: &Sender<UpdateStatus>) -> Result<UpdateStatus, String> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent("atrofac-updater")
+        .build()
+        .map_err(|err| err.to_string())?;
+    let release: Release = client
+        .get(RELEASES_URL)
+        .send()
+        .map_err(|err| err.to_string())?
+        .json()
+        .map_err(|err| err.to_string())?;
+
+    let latest_version = release.tag_name.trim_start_matches('v');
+    if !is_newer(latest_version, current_version) {
+        return Ok(UpdateStatus::UpToDate);
+    }
+    let _ = tx.send(UpdateStatus::UpdateAvailable {
+        version: latest_version.to_owned(),
+    });
+
+    let asset = release
+        .assets
+        .iter()
+        .find(|asset| asset.name.ends_with(".exe"))
+        .ok_or_else(|| "Release has no Windows executable asset".to_owned())?;
+    let checksums_asset = release
+        .assets
+        .iter()
+        .find(|asset| asset.name == CHECKSUMS_ASSET_NAME)
+        .ok_or_else(|| format!("Release has no {} asset to verify against", CHECKSUMS_ASSET_NAME))?;
+
+    let _ = tx.send(UpdateStatus::Downloading {
+        version: latest_version.to_owned(),
+    });
+    let bytes = client
+        .get(&asset.browser_download_url)
+        .send()
+        .and_then(|resp| resp.bytes())
+        .map_err(|err| err.to_string())?;
+    if bytes.is_empty() {
+        return Err("Downloaded update is empty".to_owned());
+    }
+
+    let checksums_text = client
+        .get(&checksums_asset.browser_download_url)
+        .send()
+        .and_then(|resp| resp.text())
+        .map_err(|err| err.to_string())?;
+    let expected_digest = expected_digest_for(&checksums_text, &asset.name)
+        .ok_or_else(|| format!("{} has no entry for {}", CHECKSUMS_ASSET_NAME, asset.name))?;
+
+    let actual_digest = hex_sha256(&bytes);
+    if !actual_digest.eq_ignore_ascii_case(&expected_digest) {
+        return Err(format!(
+            "Checksum mismatch for {}: expected {}, got {}",
+            asset.name, expected_digest, actual_digest
+        ));
+    }
+
+    let current_exe = env::current_exe().map_err(|err| err.to_string())?;
+    let staged_exe = staged_path(&current_exe);
+    fs::write(&staged_exe, &bytes).map_err(|err| err.to_string())?;
+
+    Ok(UpdateStatus::Ready {
+        version: latest_version.to_owned(),
+    })
+}
+
+fn hex_sha256(bytes: &[u8]) -> String {
+    let digest = Sha256::digest(bytes);
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Parses a `sha256sum`-style listing and returns the digest for `file_name`,
+/// if present.
+fn expected_digest_for(checksums_text: &str, file_name: &str) -> Option<String> {
+    checksums_text.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let digest = parts.next()?;
+        let name = parts.next()?.trim_start_matches('*');
+        (name == file_name).then(|| digest.to_owned())
+    })
+}
+
+fn staged_path(current_exe: &std::path::Path) -> PathBuf {
+    current_exe.with_extension("new.exe")
+}
+
+fn backup_path(current_exe: &std::path::Path) -> PathBuf {
+    current_exe.with_extension("old.exe")
+}
+
+/// Checks for a staged update and, if present, swaps it into place and
+/// relaunches. Returns `true` if a relaunch was started, in which case the
+/// caller must exit immediately.
+pub fn apply_staged_update_if_present() -> Result<bool, AfErr> {
+    let current_exe = env::current_exe()?;
+    let staged_exe = staged_path(&current_exe);
+    if !staged_exe.exists() {
+        return Ok(false);
+    }
+
+    let backup_exe = backup_path(&current_exe);
+    // best-effort cleanup of a backup left by a previous swap
+    let _ = fs::remove_file(&backup_exe);
+    fs::rename(&current_exe, &backup_exe)?;
+    fs::rename(&staged_exe, &current_exe)?;
+
+    std::process::Command::new(&current_exe).spawn()?;
+    Ok(true)
+}